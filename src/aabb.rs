@@ -0,0 +1,60 @@
+use super::{Ray, Vec3, Point3};
+
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Aabb { min, max }
+    }
+
+    pub fn hit(&self, ray: &Ray, mut t_min: f32, mut t_max: f32) -> bool {
+        let origin = (ray.origin.x(), ray.origin.y(), ray.origin.z());
+        let direction = (ray.direction.x(), ray.direction.y(), ray.direction.z());
+        let min = (self.min.x(), self.min.y(), self.min.z());
+        let max = (self.max.x(), self.max.y(), self.max.z());
+
+        let axes = [
+            (origin.0, direction.0, min.0, max.0),
+            (origin.1, direction.1, min.1, max.1),
+            (origin.2, direction.2, min.2, max.2),
+        ];
+
+        for &(o, d, lo, hi) in axes.iter() {
+            let inv_d = 1.0 / d;
+            let mut t0 = (lo - o) * inv_d;
+            let mut t1 = (hi - o) * inv_d;
+
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn surrounding_box(&self, other: &Aabb) -> Aabb {
+        let min = Vec3(
+            self.min.x().min(other.min.x()),
+            self.min.y().min(other.min.y()),
+            self.min.z().min(other.min.z()),
+        );
+        let max = Vec3(
+            self.max.x().max(other.max.x()),
+            self.max.y().max(other.max.y()),
+            self.max.z().max(other.max.z()),
+        );
+
+        Aabb::new(min, max)
+    }
+}
@@ -1,6 +1,8 @@
 use super::Ray;
 use super::{Vec3, Point3, Colour};
 use crate::Material;
+use crate::Aabb;
+use crate::Rng;
 use std::sync::Arc;
 
 pub struct HitRecord {
@@ -30,38 +32,68 @@ impl HitRecord {
     }
 }
 
-pub trait Hit {
+pub trait Hit: Send {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord>;
+    fn bounding_box(&self) -> Option<Aabb>;
 }
 
-pub struct HitList(Vec<Box<dyn Hit + Sync + 'static>>);
+pub struct BvhNode {
+    left: Arc<dyn Hit + Sync + 'static>,
+    right: Arc<dyn Hit + Sync + 'static>,
+    bbox: Aabb,
+}
 
-impl HitList {
-    pub fn new() -> Self {
-        HitList(Vec::new())
-    }
+impl BvhNode {
+    pub fn new(mut objects: Vec<Arc<dyn Hit + Sync + 'static>>, rng: &mut Rng) -> Self {
+        let axis = (rng.gen_f32() * 3.0) as usize;
 
-    pub fn add<H: Hit + 'static + Sync>(&mut self, obj: H) {
-        self.0.push(Box::new(obj));
-    }
+        let box_min = |o: &Arc<dyn Hit + Sync + 'static>| {
+            let bbox = o.bounding_box().expect("BvhNode requires bounded objects");
+            match axis {
+                0 => bbox.min.x(),
+                1 => bbox.min.y(),
+                _ => bbox.min.z(),
+            }
+        };
+
+        objects.sort_by(|a, b| box_min(a).partial_cmp(&box_min(b)).unwrap());
+
+        let (left, right): (Arc<dyn Hit + Sync + 'static>, Arc<dyn Hit + Sync + 'static>) =
+            if objects.len() == 1 {
+                let only = objects.pop().unwrap();
+                (only.clone(), only)
+            } else if objects.len() == 2 {
+                let second = objects.pop().unwrap();
+                let first = objects.pop().unwrap();
+                (first, second)
+            } else {
+                let split = objects.len() / 2;
+                let right_half = objects.split_off(split);
+                (Arc::new(BvhNode::new(objects, rng)), Arc::new(BvhNode::new(right_half, rng)))
+            };
 
-    pub fn clear(&mut self) {
-        self.0.clear();
+        let bbox = left.bounding_box()
+            .expect("BvhNode requires bounded objects")
+            .surrounding_box(&right.bounding_box().expect("BvhNode requires bounded objects"));
+
+        BvhNode { left, right, bbox }
     }
 }
 
-impl Hit for HitList {
+impl Hit for BvhNode {
     fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        let mut closest_so_far = t_max;
-        let mut record = None;
-
-        for o in self.0.iter() {
-            if let Some(new_rec) = o.hit(ray, t_min, closest_so_far) {
-                closest_so_far = new_rec.t;
-                record.replace(new_rec);
-            }
+        if !self.bbox.hit(ray, t_min, t_max) {
+            return None;
         }
 
-        record
+        let left_hit = self.left.hit(ray, t_min, t_max);
+        let closest_so_far = left_hit.as_ref().map_or(t_max, |rec| rec.t);
+        let right_hit = self.right.hit(ray, t_min, closest_so_far);
+
+        right_hit.or(left_hit)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
     }
 }
\ No newline at end of file
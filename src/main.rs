@@ -3,36 +3,41 @@
 mod vec3;
 mod hit;
 mod material;
+mod aabb;
+mod rng;
 
 use vec3::{Vec3, Point3, Colour};
-use hit::{HitRecord, Hit, HitList};
-use material::{Material, Metal, Lambertian};
+use hit::{HitRecord, Hit, BvhNode};
+use material::{Material, Metal, Lambertian, Dielectric};
+use aabb::Aabb;
+use rng::Rng;
 use std::sync::Arc;
+use image::{RgbImage, Rgb};
+use rayon::prelude::*;
 
 
 const ASPECT_RATIO: f32 = 16.0 / 9.0;
 const IMAGE_WIDTH: usize = 384;
 const IMAGE_HEIGHT: usize = (IMAGE_WIDTH as f32 / ASPECT_RATIO) as usize;
+const OUTPUT_PATH: &str = "output.png";
 
-const VIEWPORT_HEIGHT: f32 = 2.0;
-const VIEWPORT_WIDTH: f32 = ASPECT_RATIO * VIEWPORT_HEIGHT;
-const FOCAL_LENGTH: f32 = 1.0;
-
-fn write_colour(colour: Colour, samples_per_pixel: usize) {
+fn colour_to_rgb(colour: Colour, samples_per_pixel: usize) -> Rgb<u8> {
     let scale = 1.0 / samples_per_pixel as f32;
     let r = (colour.0 * scale).sqrt();
     let g = (colour.1 * scale).sqrt();
     let b = (colour.2 * scale).sqrt();
 
-    let r = (255.999 * r.clamp(0.0, 0.999)) as u32;
-    let g = (255.999 * g.clamp(0.0, 0.999)) as u32;
-    let b = (255.999 * b.clamp(0.0, 0.999)) as u32;
-    println!("{} {} {}", r, g, b);
+    Rgb([
+        (255.999 * r.clamp(0.0, 0.999)) as u8,
+        (255.999 * g.clamp(0.0, 0.999)) as u8,
+        (255.999 * b.clamp(0.0, 0.999)) as u8,
+    ])
 }
 
 pub struct Ray {
     pub origin: Point3,
     pub direction: Vec3,
+    pub time: f32,
 }
 
 impl Ray {
@@ -40,14 +45,14 @@ impl Ray {
         self.origin + t * self.direction
     }
 
-    fn colour(&self, world: &impl Hit, max_depth: usize) -> Colour {
+    fn colour(&self, world: &impl Hit, max_depth: usize, rng: &mut Rng) -> Colour {
         if max_depth == 0 {
             return Colour::ZERO;
         }
 
         if let Some(rec) = world.hit(self, 0.001, f32::INFINITY) {
-            if let Some((attenuation, scattered)) = rec.material.scatter(self, &rec) {
-                attenuation * scattered.colour(world, max_depth - 1)
+            if let Some((attenuation, scattered)) = rec.material.scatter(self, &rec, rng) {
+                attenuation * scattered.colour(world, max_depth - 1, rng)
             } else {
                 Vec3::ZERO
             }
@@ -108,17 +113,75 @@ impl Hit for Sphere {
 
         None
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = Vec3(self.radius, self.radius, self.radius);
+        Some(Aabb::new(self.center - radius, self.center + radius))
+    }
 }
 
+struct MovingSphere {
+    center0: Point3,
+    center1: Point3,
+    time0: f32,
+    time1: f32,
+    radius: f32,
+    material: Arc<dyn Material + 'static + Sync>,
+}
 
-fn random_double() -> f32 {
-    random_double_in_range(0.0, 1.0)
+impl MovingSphere {
+    fn center(&self, time: f32) -> Point3 {
+        self.center0 + ((time - self.time0) / (self.time1 - self.time0)) * (self.center1 - self.center0)
+    }
 }
 
-fn random_double_in_range(min: f32, max: f32) -> f32 {
-    use rand::Rng;
+impl Hit for MovingSphere {
+    fn hit(&self, ray: &Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let center = self.center(ray.time);
+        let oc = ray.origin - center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(ray.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+
+        if discriminant > 0.0 {
+            let root = discriminant.sqrt();
+
+            let temp = (-half_b - root) / a;
+            let temp = if temp >= t_max || temp <= t_min {
+                (-half_b + root) / a
+            } else {
+                temp
+            };
+
+            if temp < t_max && temp > t_min {
+                let point = ray.at(temp);
+                return Some(HitRecord::new(ray,
+                                           point,
+                                           (point - center) / self.radius,
+                                           temp,
+                                           self.material.clone()));
+            }
+        }
+
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let radius = Vec3(self.radius, self.radius, self.radius);
+        let box0 = Aabb::new(self.center0 - radius, self.center0 + radius);
+        let box1 = Aabb::new(self.center1 - radius, self.center1 + radius);
+        Some(box0.surrounding_box(&box1))
+    }
+}
 
-    rand::thread_rng().gen_range(min, max)
+struct CameraSettings {
+    vfov_degrees: f32,
+    aspect_ratio: f32,
+    aperture: f32,
+    focus_dist: f32,
+    time0: f32,
+    time1: f32,
 }
 
 struct Camera {
@@ -126,46 +189,84 @@ struct Camera {
     lower_left_corner: Point3,
     horizontal: Vec3,
     vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    lens_radius: f32,
+    time0: f32,
+    time1: f32,
 }
 
 impl Camera {
-    pub fn new() -> Self {
-        let origin = Vec3(0.0, 0.0, 0.0);
-        let horizontal = Vec3(VIEWPORT_WIDTH, 0.0, 0.0);
-        let vertical = Vec3(0.0, VIEWPORT_HEIGHT, 0.0);
+    pub fn new(lookfrom: Point3,
+               lookat: Point3,
+               vup: Vec3,
+               settings: CameraSettings) -> Self {
+        let theta = settings.vfov_degrees.to_radians();
+        let viewport_height = 2.0 * (theta / 2.0).tan();
+        let viewport_width = settings.aspect_ratio * viewport_height;
+
+        let w = (lookfrom - lookat).unit();
+        let u = vup.cross(w).unit();
+        let v = w.cross(u);
+
+        let origin = lookfrom;
+        let horizontal = settings.focus_dist * viewport_width * u;
+        let vertical = settings.focus_dist * viewport_height * v;
         let lower_left_corner = origin
             - horizontal / 2.0f32
             - vertical / 2.0f32
-            - Vec3(0.0, 0.0, FOCAL_LENGTH);
+            - settings.focus_dist * w;
 
         Camera {
             origin,
             horizontal,
             vertical,
             lower_left_corner,
+            u,
+            v,
+            lens_radius: settings.aperture / 2.0,
+            time0: settings.time0,
+            time1: settings.time1,
         }
     }
 
-    pub fn get_ray(&self, u: f32, v: f32) -> Ray {
+    pub fn get_ray(&self, s: f32, t: f32, rng: &mut Rng) -> Ray {
+        let rd = self.lens_radius * Vec3::random_in_unit_disk_with(rng);
+        let offset = self.u * rd.x() + self.v * rd.y();
+
         Ray {
-            origin: self.origin,
+            origin: self.origin + offset,
             direction: self.lower_left_corner +
-                u * self.horizontal +
-                v * self.vertical -
-                self.origin
+                s * self.horizontal +
+                t * self.vertical -
+                self.origin -
+                offset,
+            time: rng.gen_range(self.time0, self.time1),
         }
     }
 }
 
 fn main() {
-    // Header
-    println!("P3\n{} {}\n255", IMAGE_WIDTH, IMAGE_HEIGHT);
-
-    let camera = Camera::new();
+    let lookfrom = Vec3(0.0, 0.5, 1.0);
+    let lookat = Vec3(0.0, 0.2, -1.5);
+    let camera = Camera::new(
+        lookfrom,
+        lookat,
+        Vec3(0.0, 1.0, 0.0),
+        CameraSettings {
+            vfov_degrees: 60.0,
+            aspect_ratio: ASPECT_RATIO,
+            aperture: 0.1,
+            focus_dist: (lookfrom - lookat).length(),
+            time0: 0.0,
+            time1: 1.0,
+        },
+    );
+
+    const BASE_SEED: u64 = 0xc0ffee;
+    const SCENE_SEED: u64 = 0x5eed;
 
     let world = {
-        let mut world = HitList::new();
-
         let bg_material = Arc::new(Lambertian::new(Colour::UNIT / 2.0 + Colour::Y / 2.0));
 
         let default_material = Arc::new(Lambertian::new(Colour::X));
@@ -173,77 +274,149 @@ fn main() {
         let metal_material0 = Arc::new(Metal::new(Colour::UNIT * 0.8, 0.0));
         let metal_material1 = Arc::new(Metal::new(Colour::UNIT * 0.8, 0.3));
         let metal_material2 = Arc::new(Metal::new(Colour::UNIT * 0.8, 0.8));
+        let glass_material = Arc::new(Dielectric::new(1.5));
+
+        let objects: Vec<Arc<dyn Hit + Sync>> = vec![
+            Arc::new(Sphere {
+                center: Vec3(0f32, -100.5f32, -1f32),
+                radius: 100.0,
+                material: bg_material.clone(),
+            }),
+            Arc::new(Sphere {
+                center: Vec3(0f32, 0.2f32, -1.5f32),
+                radius: 0.5,
+                material: default_material.clone(),
+            }),
+            Arc::new(Sphere {
+                center: Vec3(0f32, 1.2f32, -1.5f32),
+                radius: 0.5,
+                material: metal_material0,
+            }),
+            Arc::new(MovingSphere {
+                center0: Vec3(1f32, 0.2f32, -1.5f32),
+                center1: Vec3(1f32, 0.4f32, -1.5f32),
+                time0: 0.0,
+                time1: 1.0,
+                radius: 0.5,
+                material: metal_material1,
+            }),
+            Arc::new(Sphere {
+                center: Vec3(-1f32, 0.2f32, -1.5f32),
+                radius: 0.5,
+                material: metal_material2,
+            }),
+            Arc::new(Sphere {
+                center: Vec3(-0.5f32, -0.3f32, -0.9f32),
+                radius: 0.2,
+                material: glass_material,
+            }),
+        ];
+
+        let mut scene_rng = Rng::new(SCENE_SEED, 0);
+        BvhNode::new(objects, &mut scene_rng)
+    };
 
-        world.add(Sphere {
-            center: Vec3(0f32, -100.5f32, -1f32),
-            radius: 100.0,
-            material: bg_material.clone(),
-        });
-
-        world.add(Sphere {
-            center: Vec3(0f32, 0.2f32, -1.5f32),
-            radius: 0.5,
-            material: default_material.clone(),
-        });
-
-        world.add(Sphere {
-            center: Vec3(0f32, 1.2f32, -1.5f32),
-            radius: 0.5,
-            material: metal_material0,
-        });
+    const SAMPLES_PER_PIXELS: usize = 250;
+    const MAX_DEPTH: usize = 100;
 
-        world.add(Sphere {
-            center: Vec3(1f32, 0.2f32, -1.5f32),
-            radius: 0.5,
-            material: metal_material1,
-        });
+    let frame = render(&camera, &world, SAMPLES_PER_PIXELS, MAX_DEPTH, BASE_SEED);
 
-        world.add(Sphere {
-            center: Vec3(-1f32, 0.2f32, -1.5f32),
-            radius: 0.5,
-            material: metal_material2,
-        });
+    let mut image = RgbImage::new(IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32);
+    for (row, scanline) in frame.iter().enumerate() {
+        for (col, &pixel_colour) in scanline.iter().enumerate() {
+            image.put_pixel(col as u32, row as u32, colour_to_rgb(pixel_colour, SAMPLES_PER_PIXELS));
+        }
+    }
 
-        world
-    };
+    image.save(OUTPUT_PATH).expect("failed to write output image");
+}
 
-    const SAMPLES_PER_PIXELS: usize = 250;
+fn sample_pixel(camera: &Camera,
+                 world: &impl Hit,
+                 i: usize,
+                 j: usize,
+                 samples_per_pixel: usize,
+                 max_depth: usize,
+                 rng: &mut Rng) -> Colour {
+    let mut pixel_colour = Vec3::ZERO;
+
+    for _ in 0..samples_per_pixel {
+        let u = (i as f32 + rng.gen_f32()) / (IMAGE_WIDTH - 1) as f32;
+        let v = (j as f32 + rng.gen_f32()) / (IMAGE_HEIGHT - 1) as f32;
+        let r = camera.get_ray(u, v, rng);
+        pixel_colour += r.colour(world, max_depth, rng);
+    }
 
-    let mut frame = [[Colour::ZERO; IMAGE_WIDTH]; IMAGE_HEIGHT];
-
-    // use rayon::prelude::*;
-    // (&mut frame[..]).par_iter_mut().enumerate().for_each(|(idx, r)| {
-    //     for i in 0..IMAGE_WIDTH {
-    //         let mut pixel_color = Vec3::ZERO;
-    //         for _ in 0..SAMPLES_PER_PIXELS {
-    //             let u = (i as f32 + random_double()) / (IMAGE_WIDTH - 1) as f32;
-    //             let v = (idx as f32 + random_double()) / (IMAGE_HEIGHT - 1) as f32;
-    //             let r = camera.get_ray(u, v);
-    //             pixel_color += r.colour(&world, 100);
-    //         }
-    //         r[i] = pixel_color;
-    //     }
-    // });
-
-    for j in (0..IMAGE_HEIGHT).rev() {
-        for i in 0..IMAGE_WIDTH {
-            let mut pixel_color = Vec3::ZERO;
-            for _ in 0..SAMPLES_PER_PIXELS {
-                let u = (i as f32 + random_double()) / (IMAGE_WIDTH - 1) as f32;
-                let v = (j as f32 + random_double()) / (IMAGE_HEIGHT - 1) as f32;
-                let r = camera.get_ray(u, v);
-                pixel_color += r.colour(&world, 100);
-            }
+    pixel_colour
+}
 
-            frame[j][i] = pixel_color;
+#[allow(dead_code)]
+fn render_serial(camera: &Camera,
+                  world: &impl Hit,
+                  samples_per_pixel: usize,
+                  max_depth: usize,
+                  base_seed: u64) -> Vec<Vec<Colour>> {
+    let mut frame = vec![vec![Colour::ZERO; IMAGE_WIDTH]; IMAGE_HEIGHT];
+
+    for (row, scanline) in frame.iter_mut().enumerate() {
+        let j = IMAGE_HEIGHT - 1 - row;
+        for (i, pixel) in scanline.iter_mut().enumerate() {
+            let mut rng = Rng::for_pixel(base_seed, i, j);
+            *pixel = sample_pixel(camera, world, i, j, samples_per_pixel, max_depth, &mut rng);
         }
     }
 
-    for j in (0..IMAGE_HEIGHT).rev() {
-        for i in 0..IMAGE_WIDTH {
-            write_colour(frame[j][i], SAMPLES_PER_PIXELS);
+    frame
+}
+
+fn render(camera: &Camera,
+          world: &(impl Hit + Sync),
+          samples_per_pixel: usize,
+          max_depth: usize,
+          base_seed: u64) -> Vec<Vec<Colour>> {
+    let mut frame = vec![vec![Colour::ZERO; IMAGE_WIDTH]; IMAGE_HEIGHT];
+
+    frame.par_iter_mut().enumerate().for_each(|(row, scanline)| {
+        let j = IMAGE_HEIGHT - 1 - row;
+        for (i, pixel) in scanline.iter_mut().enumerate() {
+            let mut rng = Rng::for_pixel(base_seed, i, j);
+            *pixel = sample_pixel(camera, world, i, j, samples_per_pixel, max_depth, &mut rng);
         }
-    }
+    });
+
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_and_parallel_renders_agree() {
+        let camera = Camera::new(
+            Vec3(0.0, 0.0, 1.0),
+            Vec3(0.0, 0.0, -1.0),
+            Vec3(0.0, 1.0, 0.0),
+            CameraSettings {
+                vfov_degrees: 60.0,
+                aspect_ratio: ASPECT_RATIO,
+                aperture: 0.0,
+                focus_dist: 1.0,
+                time0: 0.0,
+                time1: 1.0,
+            },
+        );
+
+        let world = Sphere {
+            center: Vec3(0.0, 0.0, -1.0),
+            radius: 0.5,
+            material: Arc::new(Lambertian::new(Colour::UNIT)),
+        };
 
+        let base_seed = 42;
+        let serial = render_serial(&camera, &world, 4, 4, base_seed);
+        let parallel = render(&camera, &world, 4, 4, base_seed);
 
+        assert_eq!(serial, parallel);
+    }
 }
@@ -1,7 +1,7 @@
-use crate::{Ray, HitRecord, Vec3, Colour};
+use crate::{Ray, HitRecord, Vec3, Colour, Rng};
 
 pub trait Material: Send {
-    fn scatter (&self, ray: &Ray, hit_rec: &HitRecord) -> Option<(Colour, Ray)>;
+    fn scatter (&self, ray: &Ray, hit_rec: &HitRecord, rng: &mut Rng) -> Option<(Colour, Ray)>;
 }
 
 #[derive(Clone, Debug)]
@@ -18,11 +18,11 @@ impl Lambertian {
 }
 
 impl Material for Lambertian {
-    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord) -> Option<(Colour, Ray)> {
-        let scatter_direction = hit_rec.normal + Vec3::random_unit_vector();
+    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord, rng: &mut Rng) -> Option<(Colour, Ray)> {
+        let scatter_direction = hit_rec.normal + Vec3::random_unit_vector_with(rng);
         Some((
             self.albedo,
-            Ray{origin: hit_rec.p, direction: scatter_direction},
+            Ray{origin: hit_rec.p, direction: scatter_direction, time: ray.time},
         ))
     }
 }
@@ -43,7 +43,7 @@ impl Metal {
 }
 
 impl Material for Metal {
-    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord) -> Option<(Colour, Ray)> {
+    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord, rng: &mut Rng) -> Option<(Colour, Ray)> {
         fn reflect(v: Vec3, n: Vec3) -> Vec3 {
             v - 2.0 * v.dot(n) * n
         }
@@ -52,7 +52,8 @@ impl Material for Metal {
         if reflected.dot(hit_rec.normal) > 0.0 {
             let scattered = Ray {
                 origin: hit_rec.p,
-                direction: reflected + self.fuzz * Vec3::random_in_unit_sphere(),
+                direction: reflected + self.fuzz * Vec3::random_in_unit_sphere_with(rng),
+                time: ray.time,
             };
             return Some((self.albedo, scattered))
         } else {
@@ -60,3 +61,46 @@ impl Material for Metal {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug)]
+pub struct Dielectric {
+    ior: f32,
+}
+
+impl Dielectric {
+    pub fn new(ior: f32) -> Self {
+        Dielectric { ior }
+    }
+
+    fn reflectance(cos_theta: f32, ratio: f32) -> f32 {
+        let r0 = ((1.0 - ratio) / (1.0 + ratio)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos_theta).powi(5)
+    }
+}
+
+impl Material for Dielectric {
+    fn scatter(&self, ray: &Ray, hit_rec: &HitRecord, rng: &mut Rng) -> Option<(Colour, Ray)> {
+        fn reflect(v: Vec3, n: Vec3) -> Vec3 {
+            v - 2.0 * v.dot(n) * n
+        }
+
+        let ratio = if hit_rec.front_face { 1.0 / self.ior } else { self.ior };
+
+        let uv = ray.direction.unit();
+        let cos_theta = (-uv).dot(hit_rec.normal).min(1.0);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+        let direction = if ratio * sin_theta > 1.0 || Self::reflectance(cos_theta, ratio) > rng.gen_f32() {
+            reflect(uv, hit_rec.normal)
+        } else {
+            let r_out_perp = ratio * (uv + cos_theta * hit_rec.normal);
+            let r_out_parallel = -(1.0 - r_out_perp.length_squared()).abs().sqrt() * hit_rec.normal;
+            r_out_perp + r_out_parallel
+        };
+
+        Some((
+            Colour::UNIT,
+            Ray { origin: hit_rec.p, direction, time: ray.time },
+        ))
+    }
+}
@@ -0,0 +1,53 @@
+pub struct Rng {
+    state: u64,
+    inc: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64, stream: u64) -> Self {
+        let mut rng = Rng { state: 0, inc: (stream << 1) | 1 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    pub fn for_pixel(base_seed: u64, x: usize, y: usize) -> Self {
+        let coords = (x as u64) << 32 | (y as u64);
+        Rng::new(base_seed ^ coords, coords)
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.state = old_state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(self.inc);
+
+        let xorshifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rot = (old_state >> 59) as u32;
+        xorshifted.rotate_right(rot)
+    }
+
+    pub fn gen_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn gen_range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.gen_f32()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_and_coords_produce_same_sequence() {
+        let mut a = Rng::for_pixel(7, 3, 5);
+        let mut b = Rng::for_pixel(7, 3, 5);
+
+        for _ in 0..16 {
+            assert_eq!(a.gen_f32(), b.gen_f32());
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::ops::{Add, Neg, AddAssign, MulAssign, DivAssign, Sub, SubAssign, Mul, Div};
+use crate::Rng;
 
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -11,30 +12,24 @@ impl Vec3 {
     pub const Z: Vec3 = Vec3(0.0, 0.0, 1.0);
     pub const UNIT: Vec3 = Vec3(1.0, 1.0, 1.0);
 
-    pub fn random() -> Self {
-        Self(super::random_double(),
-             super::random_double(),
-             super::random_double())
+    pub fn random_in_range_with(rng: &mut Rng, min: f32, max: f32) -> Self {
+        Self(rng.gen_range(min, max),
+             rng.gen_range(min, max),
+             rng.gen_range(min, max))
     }
 
-    pub fn random_in_range(min: f32, max: f32) -> Self {
-        Self(super::random_double_in_range(min, max),
-             super::random_double_in_range(min, max),
-             super::random_double_in_range(min, max))
-    }
-
-    pub fn random_in_unit_sphere() -> Self {
+    pub fn random_in_unit_sphere_with(rng: &mut Rng) -> Self {
         loop {
-            let p = Self::random_in_range(-1.0, 1.0);
+            let p = Self::random_in_range_with(rng, -1.0, 1.0);
             if p.length_squared() < 1.0 {
                 break p;
             }
         }
     }
 
-    pub fn random_unit_vector() -> Self {
-        let a = super::random_double_in_range(0.0, std::f32::consts::PI * 2.0);
-        let z = super::random_double_in_range(-1.0, 1.0);
+    pub fn random_unit_vector_with(rng: &mut Rng) -> Self {
+        let a = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+        let z = rng.gen_range(-1.0, 1.0);
         let r = (1.0 - z * z).sqrt();
         Vec3(
             r * a.cos(),
@@ -43,13 +38,12 @@ impl Vec3 {
         )
     }
 
-    pub fn random_in_hemisphere(&self) -> Self {
-        let in_unit_sphere = Self::random_in_unit_sphere();
-
-        if self.dot(in_unit_sphere) > 0.0 {
-            in_unit_sphere
-        } else {
-            -in_unit_sphere
+    pub fn random_in_unit_disk_with(rng: &mut Rng) -> Self {
+        loop {
+            let p = Vec3(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), 0.0);
+            if p.length_squared() < 1.0 {
+                break p;
+            }
         }
     }
 
@@ -84,7 +78,7 @@ impl Neg for Vec3 {
     type Output = Vec3;
 
     fn neg(self) -> Self::Output {
-        Vec3(self.0, self.1, self.2)
+        Vec3(-self.0, -self.1, -self.2)
     }
 }
 